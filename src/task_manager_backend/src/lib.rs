@@ -1,24 +1,360 @@
 use candid::CandidType;
+use chrono::{DateTime, Datelike, NaiveDateTime, Utc};
+use petgraph::algo::{has_path_connecting, toposort};
+use petgraph::graphmap::DiGraphMap;
+use roaring::RoaringBitmap;
 use serde::{Serialize, Deserialize};
-use std::{cell::RefCell, collections::HashMap};
+use std::collections::Bound::{Excluded, Unbounded};
+use std::{cell::RefCell, collections::BTreeMap, collections::HashMap};
 use std::cell::Cell;
 
+const TASKWARRIOR_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
 // Task struct
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
 struct Task {
     id: u64,
+    uuid: String,
     title: String,
     description: String,
-    done: bool,
+    status: TaskStatus,
     is_important: bool,
     created_at: u64,
     updated_at: u64,
+    started_at: Option<u64>,
+    due_at: Option<u64>,
+    dependencies: Vec<u64>,
+    // (timestamp, note) pairs, round-tripped through Taskwarrior's
+    // "annotations" array on import/export.
+    annotations: Vec<(u64, String)>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+enum TaskStatus {
+    Pending,
+    Started,
+    Completed,
 }
 
 // Task Manager struct
 thread_local! {
     static TASKS: RefCell<HashMap<u64, Task>> = RefCell::default();
     static NEXT_ID: Cell<u64> = Cell::new(0);
+    // Edges point from a dependency to the task that depends on it, so a
+    // topological sort of this graph is a valid task execution order.
+    static DEP_GRAPH: RefCell<DiGraphMap<u64, ()>> = RefCell::default();
+
+    // Secondary indexes mirroring TASKS, kept in sync by every mutator below
+    // so queries are bitmap lookups instead of full-map scans. Task ids are
+    // cast to u32 since RoaringBitmap only stores 32-bit integers.
+    static ALL_IDS: RefCell<RoaringBitmap> = RefCell::default();
+    static DONE_INDEX: RefCell<RoaringBitmap> = RefCell::default();
+    static STARTED_INDEX: RefCell<RoaringBitmap> = RefCell::default();
+    static IMPORTANT_INDEX: RefCell<RoaringBitmap> = RefCell::default();
+    static CREATED_INDEX: RefCell<BTreeMap<u64, RoaringBitmap>> = RefCell::default();
+    static UPDATED_INDEX: RefCell<BTreeMap<u64, RoaringBitmap>> = RefCell::default();
+    // Only tasks with a due date have an entry here.
+    static DUE_INDEX: RefCell<BTreeMap<u64, RoaringBitmap>> = RefCell::default();
+}
+
+fn timestamp_index_insert(index: &RefCell<BTreeMap<u64, RoaringBitmap>>, timestamp: u64, id: u32) {
+    index.borrow_mut().entry(timestamp).or_default().insert(id);
+}
+
+fn timestamp_index_remove(index: &RefCell<BTreeMap<u64, RoaringBitmap>>, timestamp: u64, id: u32) {
+    let mut index = index.borrow_mut();
+    if let Some(bitmap) = index.get_mut(&timestamp) {
+        bitmap.remove(id);
+        if bitmap.is_empty() {
+            index.remove(&timestamp);
+        }
+    }
+}
+
+fn timestamp_index_after(index: &BTreeMap<u64, RoaringBitmap>, timestamp: u64) -> RoaringBitmap {
+    index
+        .range((Excluded(timestamp), Unbounded))
+        .fold(RoaringBitmap::new(), |mut acc, (_, bitmap)| {
+            acc |= bitmap;
+            acc
+        })
+}
+
+fn timestamp_index_before(index: &BTreeMap<u64, RoaringBitmap>, timestamp: u64) -> RoaringBitmap {
+    index
+        .range((Unbounded, Excluded(timestamp)))
+        .fold(RoaringBitmap::new(), |mut acc, (_, bitmap)| {
+            acc |= bitmap;
+            acc
+        })
+}
+
+// Resolves a boolean flag query (e.g. "done == true") against its bitmap,
+// falling back to ALL_IDS minus the bitmap for the "false" side.
+fn ids_for_flag(flag_index: &RoaringBitmap, value: bool) -> RoaringBitmap {
+    if value {
+        flag_index.clone()
+    } else {
+        ALL_IDS.with(|all| &*all.borrow() - flag_index)
+    }
+}
+
+fn materialize(ids: &RoaringBitmap) -> Vec<Task> {
+    TASKS.with(|tasks| {
+        let tasks = tasks.borrow();
+        ids.iter().filter_map(|id| tasks.get(&(id as u64)).cloned()).collect()
+    })
+}
+
+// Re-homes a task's updated_at entry in UPDATED_INDEX and bumps the
+// timestamp, used by every mutator that touches a task.
+fn touch_updated_at(task: &mut Task, id32: u32) {
+    reindex_updated_at(task, id32, ic_cdk::api::time());
+}
+
+// Pure core of touch_updated_at, split out so the index bookkeeping is
+// testable without a live IC time syscall.
+fn reindex_updated_at(task: &mut Task, id32: u32, now: u64) {
+    UPDATED_INDEX.with(|index| timestamp_index_remove(index, task.updated_at, id32));
+    task.updated_at = now;
+    UPDATED_INDEX.with(|index| timestamp_index_insert(index, task.updated_at, id32));
+}
+
+// Moves a task between DONE_INDEX/STARTED_INDEX as its status changes, so
+// status-based queries stay bitmap lookups instead of scans.
+fn set_status(task: &mut Task, id32: u32, new_status: TaskStatus) {
+    if task.status == new_status {
+        return;
+    }
+
+    match task.status {
+        TaskStatus::Completed => DONE_INDEX.with(|bitmap| bitmap.borrow_mut().remove(id32)),
+        TaskStatus::Started => STARTED_INDEX.with(|bitmap| bitmap.borrow_mut().remove(id32)),
+        TaskStatus::Pending => false,
+    };
+    match new_status {
+        TaskStatus::Completed => DONE_INDEX.with(|bitmap| bitmap.borrow_mut().insert(id32)),
+        TaskStatus::Started => STARTED_INDEX.with(|bitmap| bitmap.borrow_mut().insert(id32)),
+        TaskStatus::Pending => false,
+    };
+
+    task.status = new_status;
+}
+
+// Not a real RFC 4122 UUID (the canister has no access to a CSPRNG without an
+// inter-canister call) but matches the 8-4-4-4-12 hex layout Taskwarrior and
+// other tools expect, and is stable and unique per task id.
+fn generate_uuid(id: u64, timestamp: u64) -> String {
+    let a = timestamp ^ id.wrapping_mul(0x9E3779B97F4A7C15);
+    let b = id.rotate_left(17) ^ timestamp.rotate_right(23);
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        (a >> 32) as u32,
+        (a & 0xffff) as u16,
+        ((a >> 16) & 0xffff) as u16,
+        (b & 0xffff) as u16,
+        b >> 16,
+    )
+}
+
+// Hands out the next task id, guarding against the bitmap indexes' 32-bit
+// range: once NEXT_ID would overflow u32, `id as u32` casts elsewhere would
+// silently wrap and collide with an existing task's index entries.
+fn allocate_task_id() -> Result<u64, String> {
+    NEXT_ID.with(|id| {
+        let next_id = id.get();
+        if next_id > u32::MAX as u64 {
+            return Err(TaskError::CapacityExceeded.into());
+        }
+        id.set(next_id + 1);
+        Ok(next_id)
+    })
+}
+
+// Reserves `count` contiguous ids as a single atomic step, so a batch
+// import can check the whole batch fits the 32-bit index range before
+// any task in it is inserted, instead of discovering a shortfall midway
+// through a commit loop.
+fn allocate_task_ids(count: u64) -> Result<u64, String> {
+    NEXT_ID.with(|id| {
+        let next_id = id.get();
+        if count == 0 {
+            return Ok(next_id);
+        }
+        let last_id = next_id
+            .checked_add(count - 1)
+            .ok_or_else(|| String::from(TaskError::CapacityExceeded))?;
+        if last_id > u32::MAX as u64 {
+            return Err(TaskError::CapacityExceeded.into());
+        }
+        id.set(next_id + count);
+        Ok(next_id)
+    })
+}
+
+// Inserts a fully-built task and brings every secondary index (bitmaps,
+// dependency graph) in sync with it. Shared by create_task and
+// import_tasks_json so both paths stay consistent.
+fn insert_task_with_indexes(task: Task) {
+    let id = task.id;
+    let id32 = id as u32;
+    let is_important = task.is_important;
+    let status = task.status;
+    let created_at = task.created_at;
+    let updated_at = task.updated_at;
+    let due_at = task.due_at;
+
+    TASKS.with(|tasks| tasks.borrow_mut().insert(id, task));
+    DEP_GRAPH.with(|graph| {
+        graph.borrow_mut().add_node(id);
+    });
+
+    ALL_IDS.with(|bitmap| bitmap.borrow_mut().insert(id32));
+    match status {
+        TaskStatus::Completed => DONE_INDEX.with(|bitmap| bitmap.borrow_mut().insert(id32)),
+        TaskStatus::Started => STARTED_INDEX.with(|bitmap| bitmap.borrow_mut().insert(id32)),
+        TaskStatus::Pending => false,
+    };
+    if is_important {
+        IMPORTANT_INDEX.with(|bitmap| bitmap.borrow_mut().insert(id32));
+    }
+    CREATED_INDEX.with(|index| timestamp_index_insert(index, created_at, id32));
+    UPDATED_INDEX.with(|index| timestamp_index_insert(index, updated_at, id32));
+    if let Some(due_at) = due_at {
+        DUE_INDEX.with(|index| timestamp_index_insert(index, due_at, id32));
+    }
+}
+
+// Re-homes a task's due_at entry in DUE_INDEX, used whenever a task's due
+// date is set, changed, or cleared.
+fn set_due_at(task: &mut Task, id32: u32, due_at: Option<u64>) {
+    if let Some(old) = task.due_at {
+        DUE_INDEX.with(|index| timestamp_index_remove(index, old, id32));
+    }
+    if let Some(new) = due_at {
+        DUE_INDEX.with(|index| timestamp_index_insert(index, new, id32));
+    }
+    task.due_at = due_at;
+}
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+const NANOS_PER_HOUR: u64 = NANOS_PER_SEC * 3600;
+const NANOS_PER_DAY: u64 = NANOS_PER_HOUR * 24;
+
+fn weekday_from_name(name: &str) -> Option<u32> {
+    match name {
+        "monday" => Some(0),
+        "tuesday" => Some(1),
+        "wednesday" => Some(2),
+        "thursday" => Some(3),
+        "friday" => Some(4),
+        "saturday" => Some(5),
+        "sunday" => Some(6),
+        _ => None,
+    }
+}
+
+fn weekday_of(nanos: u64) -> u32 {
+    DateTime::<Utc>::from_timestamp((nanos / NANOS_PER_SEC) as i64, 0)
+        .unwrap_or_default()
+        .weekday()
+        .num_days_from_monday()
+}
+
+// Parses a small set of human due-date expressions into an absolute
+// nanosecond timestamp relative to `now`: "today", "tomorrow", "in N
+// hours/days/weeks", and weekday names ("friday", "next friday").
+fn parse_due_date(input: &str, now: u64) -> Result<u64, String> {
+    let input = input.trim().to_lowercase();
+
+    if input == "today" {
+        return Ok(now);
+    }
+    if input == "tomorrow" {
+        return Ok(now + NANOS_PER_DAY);
+    }
+
+    if let Some(rest) = input.strip_prefix("in ") {
+        let mut parts = rest.split_whitespace();
+        let amount: u64 = parts
+            .next()
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| String::from(TaskError::InvalidInput))?;
+        let unit = parts.next().ok_or_else(|| String::from(TaskError::InvalidInput))?;
+        let unit_nanos = match unit.trim_end_matches('s') {
+            "hour" => NANOS_PER_HOUR,
+            "day" => NANOS_PER_DAY,
+            "week" => NANOS_PER_DAY * 7,
+            _ => return Err(TaskError::InvalidInput.into()),
+        };
+        return amount
+            .checked_mul(unit_nanos)
+            .and_then(|offset| now.checked_add(offset))
+            .ok_or_else(|| String::from(TaskError::InvalidInput));
+    }
+
+    let weekday_input = input.strip_prefix("next ").unwrap_or(input.as_str());
+    if let Some(target_weekday) = weekday_from_name(weekday_input) {
+        let current_weekday = weekday_of(now);
+        let mut days_ahead = (target_weekday + 7 - current_weekday) % 7;
+        if days_ahead == 0 {
+            days_ahead = 7;
+        }
+        return Ok(now + days_ahead as u64 * NANOS_PER_DAY);
+    }
+
+    Err(TaskError::InvalidInput.into())
+}
+
+fn ensure_dependencies_done(tasks: &HashMap<u64, Task>, id: u64, task: &Task) -> Result<(), String> {
+    let incomplete_deps: Vec<u64> = task
+        .dependencies
+        .iter()
+        .filter(|dep_id| tasks.get(dep_id).map(|dep| dep.status != TaskStatus::Completed).unwrap_or(true))
+        .cloned()
+        .collect();
+
+    if !incomplete_deps.is_empty() {
+        return Err(TaskError::DependencyNotDone(id, incomplete_deps).into());
+    }
+
+    Ok(())
+}
+
+// Taskwarrior 2.6 export format: uuid/status/entry/modified/description are
+// fixed fields, annotations is a fixed array, and every other field (UDAs)
+// is captured in `udas` via flatten. `udas` holds only string values, so a
+// record with a non-string extra field (number, array, object) fails to parse.
+#[derive(Serialize, Deserialize)]
+struct TaskwarriorAnnotation {
+    entry: String,
+    description: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TaskwarriorRecord {
+    uuid: String,
+    status: String,
+    entry: String,
+    modified: String,
+    description: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    annotations: Vec<TaskwarriorAnnotation>,
+    #[serde(flatten)]
+    udas: HashMap<String, String>,
+}
+
+fn format_taskwarrior_time(nanos: u64) -> String {
+    DateTime::<Utc>::from_timestamp((nanos / 1_000_000_000) as i64, 0)
+        .unwrap_or_default()
+        .format(TASKWARRIOR_TIME_FORMAT)
+        .to_string()
+}
+
+fn parse_taskwarrior_time(value: &str) -> Result<u64, String> {
+    let naive = NaiveDateTime::parse_from_str(value, TASKWARRIOR_TIME_FORMAT)
+        .map_err(|_| String::from(TaskError::InvalidInput))?;
+    Ok(naive.and_utc().timestamp_nanos_opt().unwrap_or(0) as u64)
 }
 
 #[derive(CandidType, Serialize, Deserialize, Debug)]
@@ -26,6 +362,9 @@ enum TaskError {
     NotFound,
     InvalidInput,
     DuplicateTask,
+    DependencyNotDone(u64, Vec<u64>),
+    CycleDetected(u64),
+    CapacityExceeded,
 }
 
 impl From<TaskError> for String {
@@ -34,34 +373,42 @@ impl From<TaskError> for String {
             TaskError::NotFound => "Task not found".to_string(),
             TaskError::InvalidInput => "Invalid input".to_string(),
             TaskError::DuplicateTask => "Duplicate task".to_string(),
+            TaskError::DependencyNotDone(id, deps) => {
+                format!("cannot complete task {}: dependencies not done yet: {:?}", id, deps)
+            }
+            TaskError::CycleDetected(id) => format!("dependency cycle detected at task {}", id),
+            TaskError::CapacityExceeded => "task id space exhausted".to_string(),
         }
     }
 }
 
 #[ic_cdk::update]
-fn create_task(title: String, description: String, is_important: Option<bool>) -> Result<u64, String> {
+fn create_task(title: String, description: String, is_important: Option<bool>, due_at: Option<String>) -> Result<u64, String> {
     if title.is_empty() || description.is_empty() {
         return Err(TaskError::InvalidInput.into());
     }
 
-    let id = NEXT_ID.with(|id| {
-        let next_id = id.get();
-        id.set(next_id + 1);
-        next_id
-    });
+    let id = allocate_task_id()?;
 
     let timestamp = ic_cdk::api::time();
+    let is_important = is_important.unwrap_or(false);
+    let due_at = due_at.map(|value| parse_due_date(&value, timestamp)).transpose()?;
     let task = Task {
         id,
+        uuid: generate_uuid(id, timestamp),
         title,
         description,
-        is_important: is_important.unwrap_or(false),
-        done: false,
+        is_important,
+        status: TaskStatus::Pending,
         created_at: timestamp,
         updated_at: timestamp,
+        started_at: None,
+        due_at,
+        dependencies: Vec::new(),
+        annotations: Vec::new(),
     };
 
-    TASKS.with(|tasks| tasks.borrow_mut().insert(id, task));
+    insert_task_with_indexes(task);
 
     Ok(id)
 }
@@ -79,9 +426,24 @@ fn get_all_tasks() -> Vec<Task> {
 }
 
 #[ic_cdk::update]
-fn update_task(id: u64, title: Option<String>, description: Option<String>, done: Option<bool>, is_important: Option<bool>) -> Result<bool, String> {
+fn update_task(id: u64, title: Option<String>, description: Option<String>, done: Option<bool>, is_important: Option<bool>, due_at: Option<String>) -> Result<bool, String> {
+    // Resolve every fallible input before mutating anything, so a bad
+    // `due_at` string (or an unmet dependency) can't leave other fields
+    // partially applied.
+    let parsed_due_at = due_at.map(|value| parse_due_date(&value, ic_cdk::api::time())).transpose()?;
+
+    if done == Some(true) {
+        TASKS.with(|tasks| {
+            let tasks_ref = tasks.borrow();
+            let task = tasks_ref.get(&id).ok_or_else(|| String::from(TaskError::NotFound))?;
+            ensure_dependencies_done(&tasks_ref, id, task)
+        })?;
+    }
+
     TASKS.with(|tasks| {
         if let Some(task) = tasks.borrow_mut().get_mut(&id) {
+            let id32 = id as u32;
+
             if let Some(new_title) = title {
                 task.title = new_title;
             }
@@ -89,12 +451,20 @@ fn update_task(id: u64, title: Option<String>, description: Option<String>, done
                 task.description = new_description;
             }
             if let Some(new_done) = done {
-                task.done = new_done;
+                let new_status = if new_done { TaskStatus::Completed } else { TaskStatus::Pending };
+                set_status(task, id32, new_status);
             }
             if let Some(new_is_important) = is_important {
+                IMPORTANT_INDEX.with(|bitmap| {
+                    let mut bitmap = bitmap.borrow_mut();
+                    if new_is_important { bitmap.insert(id32); } else { bitmap.remove(id32); }
+                });
                 task.is_important = new_is_important;
             }
-            task.updated_at = ic_cdk::api::time();
+            if let Some(parsed) = parsed_due_at {
+                set_due_at(task, id32, Some(parsed));
+            }
+            touch_updated_at(task, id32);
             Ok(true)
         } else {
             Err(TaskError::NotFound.into())
@@ -104,27 +474,48 @@ fn update_task(id: u64, title: Option<String>, description: Option<String>, done
 
 #[ic_cdk::update]
 fn delete_task(id: u64) -> Result<bool, String> {
-    TASKS.with(|tasks| tasks.borrow_mut().remove(&id).is_some().then(|| true).ok_or_else(|| TaskError::NotFound.into()))
+    let removed = TASKS.with(|tasks| tasks.borrow_mut().remove(&id));
+
+    let Some(removed) = removed else {
+        return Err(TaskError::NotFound.into());
+    };
+
+    DEP_GRAPH.with(|graph| {
+        graph.borrow_mut().remove_node(id);
+    });
+    TASKS.with(|tasks| {
+        for task in tasks.borrow_mut().values_mut() {
+            task.dependencies.retain(|dep_id| *dep_id != id);
+        }
+    });
+
+    let id32 = id as u32;
+    ALL_IDS.with(|bitmap| bitmap.borrow_mut().remove(id32));
+    DONE_INDEX.with(|bitmap| bitmap.borrow_mut().remove(id32));
+    STARTED_INDEX.with(|bitmap| bitmap.borrow_mut().remove(id32));
+    IMPORTANT_INDEX.with(|bitmap| bitmap.borrow_mut().remove(id32));
+    CREATED_INDEX.with(|index| timestamp_index_remove(index, removed.created_at, id32));
+    UPDATED_INDEX.with(|index| timestamp_index_remove(index, removed.updated_at, id32));
+    if let Some(due_at) = removed.due_at {
+        DUE_INDEX.with(|index| timestamp_index_remove(index, due_at, id32));
+    }
+
+    Ok(true)
 }
 
 #[ic_cdk::query]
 fn search_task_by_status(done: bool) -> Vec<Task> {
-    TASKS.with(|tasks| {
-        tasks
-            .borrow()
-            .values()
-            .filter(|task| task.done == done)
-            .cloned()
-            .collect()
-    })
+    DONE_INDEX.with(|bitmap| materialize(&ids_for_flag(&bitmap.borrow(), done)))
 }
 
 #[ic_cdk::update]
 fn mark_task_as_important(id: u64) -> Result<bool, String> {
     TASKS.with(|tasks| {
         if let Some(task) = tasks.borrow_mut().get_mut(&id) {
+            let id32 = id as u32;
+            IMPORTANT_INDEX.with(|bitmap| bitmap.borrow_mut().insert(id32));
             task.is_important = true;
-            task.updated_at = ic_cdk::api::time();
+            touch_updated_at(task, id32);
             Ok(true)
         } else {
             Err(TaskError::NotFound.into())
@@ -134,38 +525,17 @@ fn mark_task_as_important(id: u64) -> Result<bool, String> {
 
 #[ic_cdk::query]
 fn get_important_tasks() -> Vec<Task> {
-    TASKS.with(|tasks| {
-        tasks
-            .borrow()
-            .values()
-            .filter(|task| task.is_important)
-            .cloned()
-            .collect()
-    })
+    IMPORTANT_INDEX.with(|bitmap| materialize(&bitmap.borrow()))
 }
 
 #[ic_cdk::query]
 fn get_completed_tasks() -> Vec<Task> {
-    TASKS.with(|tasks| {
-        tasks
-            .borrow()
-            .values()
-            .filter(|task| task.done)
-            .cloned()
-            .collect()
-    })
+    DONE_INDEX.with(|bitmap| materialize(&bitmap.borrow()))
 }
 
 #[ic_cdk::query]
 fn get_incomplete_tasks() -> Vec<Task> {
-    TASKS.with(|tasks| {
-        tasks
-            .borrow()
-            .values()
-            .filter(|task| !task.done)
-            .cloned()
-            .collect()
-    })
+    DONE_INDEX.with(|bitmap| materialize(&ids_for_flag(&bitmap.borrow(), false)))
 }
 
 #[ic_cdk::query]
@@ -187,29 +557,148 @@ fn get_tasks_by_description(description: String) -> Vec<Task> {
 
 #[ic_cdk::query]
 fn get_tasks_by_importance_status(is_important: bool) -> Vec<Task> {
+    IMPORTANT_INDEX.with(|bitmap| materialize(&ids_for_flag(&bitmap.borrow(), is_important)))
+}
+
+// Combinator over the secondary indexes: intersects whichever filters are
+// set, e.g. query_tasks(Some(false), Some(true), None) is "important and not done".
+#[ic_cdk::query]
+fn query_tasks(done: Option<bool>, important: Option<bool>, created_after: Option<u64>) -> Vec<Task> {
+    let mut ids = ALL_IDS.with(|bitmap| bitmap.borrow().clone());
+
+    if let Some(done) = done {
+        ids &= DONE_INDEX.with(|bitmap| ids_for_flag(&bitmap.borrow(), done));
+    }
+    if let Some(important) = important {
+        ids &= IMPORTANT_INDEX.with(|bitmap| ids_for_flag(&bitmap.borrow(), important));
+    }
+    if let Some(after) = created_after {
+        ids &= CREATED_INDEX.with(|index| timestamp_index_after(&index.borrow(), after));
+    }
+
+    materialize(&ids)
+}
+
+#[ic_cdk::update]
+fn clear_completed_tasks() {
+    let completed_ids: Vec<u64> = DONE_INDEX.with(|bitmap| bitmap.borrow().iter().map(|id| id as u64).collect());
+    for id in completed_ids {
+        let _ = delete_task(id);
+    }
+}
+
+#[ic_cdk::update]
+fn mark_task_as_done(id: u64) -> Result<bool, String> {
     TASKS.with(|tasks| {
-        tasks
-            .borrow()
-            .values()
-            .filter(|task| task.is_important == is_important)
-            .cloned()
-            .collect()
+        let tasks_ref = tasks.borrow();
+        let task = tasks_ref.get(&id).ok_or_else(|| String::from(TaskError::NotFound))?;
+        ensure_dependencies_done(&tasks_ref, id, task)
+    })?;
+
+    TASKS.with(|tasks| {
+        if let Some(task) = tasks.borrow_mut().get_mut(&id) {
+            let id32 = id as u32;
+            set_status(task, id32, TaskStatus::Completed);
+            touch_updated_at(task, id32);
+            Ok(true)
+        } else {
+            Err(TaskError::NotFound.into())
+        }
     })
 }
 
+// Adding a depends_on -> task_id edge would create a cycle if task_id can
+// already reach depends_on. Split out of add_dependency so it's testable
+// without going through TASKS/DEP_GRAPH thread_locals.
+fn creates_cycle(graph: &DiGraphMap<u64, ()>, task_id: u64, depends_on: u64) -> bool {
+    has_path_connecting(graph, task_id, depends_on, None)
+}
+
 #[ic_cdk::update]
-fn clear_completed_tasks() {
+fn add_dependency(task_id: u64, depends_on: u64) -> Result<bool, String> {
+    if task_id == depends_on {
+        return Err(TaskError::InvalidInput.into());
+    }
+
+    TASKS.with(|tasks| {
+        let tasks = tasks.borrow();
+        if !tasks.contains_key(&task_id) || !tasks.contains_key(&depends_on) {
+            return Err(String::from(TaskError::NotFound));
+        }
+        Ok(())
+    })?;
+
+    DEP_GRAPH.with(|graph| {
+        let mut graph = graph.borrow_mut();
+
+        if creates_cycle(&graph, task_id, depends_on) {
+            return Err(String::from(TaskError::CycleDetected(task_id)));
+        }
+
+        graph.add_edge(depends_on, task_id, ());
+        Ok(())
+    })?;
+
+    TASKS.with(|tasks| {
+        if let Some(task) = tasks.borrow_mut().get_mut(&task_id) {
+            if !task.dependencies.contains(&depends_on) {
+                task.dependencies.push(depends_on);
+            }
+            touch_updated_at(task, task_id as u32);
+        }
+    });
+
+    Ok(true)
+}
+
+#[ic_cdk::update]
+fn remove_dependency(task_id: u64, depends_on: u64) -> Result<bool, String> {
+    DEP_GRAPH.with(|graph| {
+        graph.borrow_mut().remove_edge(depends_on, task_id);
+    });
+
+    TASKS.with(|tasks| {
+        if let Some(task) = tasks.borrow_mut().get_mut(&task_id) {
+            task.dependencies.retain(|dep_id| *dep_id != depends_on);
+            touch_updated_at(task, task_id as u32);
+            Ok(true)
+        } else {
+            Err(TaskError::NotFound.into())
+        }
+    })
+}
+
+#[ic_cdk::query]
+fn get_task_order() -> Result<Vec<u64>, String> {
+    DEP_GRAPH.with(|graph| {
+        toposort(&*graph.borrow(), None)
+            .map_err(|cycle| String::from(TaskError::CycleDetected(cycle.node_id())))
+    })
+}
+
+#[ic_cdk::update]
+fn reset_task_status(id: u64) -> Result<bool, String> {
     TASKS.with(|tasks| {
-        tasks.borrow_mut().retain(|_, task| !task.done);
+        if let Some(task) = tasks.borrow_mut().get_mut(&id) {
+            let id32 = id as u32;
+            set_status(task, id32, TaskStatus::Pending);
+            task.started_at = None;
+            touch_updated_at(task, id32);
+            Ok(true)
+        } else {
+            Err(TaskError::NotFound.into())
+        }
     })
 }
 
 #[ic_cdk::update]
-fn mark_task_as_done(id: u64) -> Result<bool, String> {
+fn start_task(id: u64) -> Result<bool, String> {
     TASKS.with(|tasks| {
         if let Some(task) = tasks.borrow_mut().get_mut(&id) {
-            task.done = true;
-            task.updated_at = ic_cdk::api::time();
+            let id32 = id as u32;
+            set_status(task, id32, TaskStatus::Started);
+            task.started_at = Some(ic_cdk::api::time());
+            touch_updated_at(task, id32);
             Ok(true)
         } else {
             Err(TaskError::NotFound.into())
@@ -218,11 +707,13 @@ fn mark_task_as_done(id: u64) -> Result<bool, String> {
 }
 
 #[ic_cdk::update]
-fn reset_task_status(id: u64) -> Result<bool, String> {
+fn stop_task(id: u64) -> Result<bool, String> {
     TASKS.with(|tasks| {
         if let Some(task) = tasks.borrow_mut().get_mut(&id) {
-            task.done = false;
-            task.updated_at = ic_cdk::api::time();
+            let id32 = id as u32;
+            set_status(task, id32, TaskStatus::Pending);
+            task.started_at = None;
+            touch_updated_at(task, id32);
             Ok(true)
         } else {
             Err(TaskError::NotFound.into())
@@ -230,6 +721,49 @@ fn reset_task_status(id: u64) -> Result<bool, String> {
     })
 }
 
+// Completes a task and returns how long it was actively in-progress
+// (nanoseconds since its started_at, which start_task sets and
+// stop_task/reset_task_status clear; 0 if started_at is unset).
+#[ic_cdk::update]
+fn complete_task(id: u64) -> Result<u64, String> {
+    TASKS.with(|tasks| {
+        let tasks_ref = tasks.borrow();
+        let task = tasks_ref.get(&id).ok_or_else(|| String::from(TaskError::NotFound))?;
+        ensure_dependencies_done(&tasks_ref, id, task)
+    })?;
+
+    TASKS.with(|tasks| {
+        if let Some(task) = tasks.borrow_mut().get_mut(&id) {
+            let id32 = id as u32;
+            let now = ic_cdk::api::time();
+            let elapsed_active_time = task.started_at.map(|started_at| now.saturating_sub(started_at)).unwrap_or(0);
+
+            set_status(task, id32, TaskStatus::Completed);
+            touch_updated_at(task, id32);
+
+            Ok(elapsed_active_time)
+        } else {
+            Err(TaskError::NotFound.into())
+        }
+    })
+}
+
+#[ic_cdk::query]
+fn get_tasks_by_status(status: TaskStatus) -> Vec<Task> {
+    match status {
+        TaskStatus::Completed => DONE_INDEX.with(|bitmap| materialize(&bitmap.borrow())),
+        TaskStatus::Started => STARTED_INDEX.with(|bitmap| materialize(&bitmap.borrow())),
+        TaskStatus::Pending => {
+            let ids = ALL_IDS.with(|all| {
+                let done = DONE_INDEX.with(|bitmap| bitmap.borrow().clone());
+                let started = STARTED_INDEX.with(|bitmap| bitmap.borrow().clone());
+                &(&*all.borrow() - &done) - &started
+            });
+            materialize(&ids)
+        }
+    }
+}
+
 #[ic_cdk::query]
 fn get_tasks_by_title(title: String) -> Vec<Task> {
     TASKS.with(|tasks| {
@@ -247,8 +781,13 @@ fn get_tasks_by_title(title: String) -> Vec<Task> {
 fn toggle_task_importance(id: u64) -> Result<bool, String> {
     TASKS.with(|tasks| {
         if let Some(task) = tasks.borrow_mut().get_mut(&id) {
+            let id32 = id as u32;
             task.is_important = !task.is_important;
-            task.updated_at = ic_cdk::api::time();
+            IMPORTANT_INDEX.with(|bitmap| {
+                let mut bitmap = bitmap.borrow_mut();
+                if task.is_important { bitmap.insert(id32); } else { bitmap.remove(id32); }
+            });
+            touch_updated_at(task, id32);
             Ok(true)
         } else {
             Err(TaskError::NotFound.into())
@@ -258,26 +797,306 @@ fn toggle_task_importance(id: u64) -> Result<bool, String> {
 
 #[ic_cdk::query]
 fn get_tasks_created_after(timestamp: u64) -> Vec<Task> {
-    TASKS.with(|tasks| {
-        tasks
-            .borrow()
-            .values()
-            .filter(|task| task.created_at > timestamp)
-            .cloned()
-            .collect()
-    })
+    CREATED_INDEX.with(|index| materialize(&timestamp_index_after(&index.borrow(), timestamp)))
 }
 
 #[ic_cdk::query]
 fn get_tasks_updated_after(timestamp: u64) -> Vec<Task> {
-    TASKS.with(|tasks| {
+    UPDATED_INDEX.with(|index| materialize(&timestamp_index_after(&index.borrow(), timestamp)))
+}
+
+#[ic_cdk::query]
+fn get_tasks_due_before(timestamp: u64) -> Vec<Task> {
+    DUE_INDEX.with(|index| materialize(&timestamp_index_before(&index.borrow(), timestamp)))
+}
+
+#[ic_cdk::query]
+fn get_overdue_tasks() -> Vec<Task> {
+    let due = DUE_INDEX.with(|index| timestamp_index_before(&index.borrow(), ic_cdk::api::time()));
+    let not_done = DONE_INDEX.with(|bitmap| ids_for_flag(&bitmap.borrow(), false));
+    materialize(&(&due & &not_done))
+}
+
+#[ic_cdk::query]
+fn export_tasks_json() -> String {
+    let records: Vec<TaskwarriorRecord> = TASKS.with(|tasks| {
         tasks
             .borrow()
             .values()
-            .filter(|task| task.updated_at > timestamp)
-            .cloned()
+            .map(|task| TaskwarriorRecord {
+                uuid: task.uuid.clone(),
+                status: match task.status {
+                    TaskStatus::Completed => "completed".to_string(),
+                    TaskStatus::Pending | TaskStatus::Started => "pending".to_string(),
+                },
+                entry: format_taskwarrior_time(task.created_at),
+                modified: format_taskwarrior_time(task.updated_at),
+                description: task.description.clone(),
+                annotations: task
+                    .annotations
+                    .iter()
+                    .map(|(timestamp, note)| TaskwarriorAnnotation {
+                        entry: format_taskwarrior_time(*timestamp),
+                        description: note.clone(),
+                    })
+                    .collect(),
+                udas: HashMap::from([
+                    ("title".to_string(), task.title.clone()),
+                    ("is_important".to_string(), task.is_important.to_string()),
+                ]),
+            })
             .collect()
-    })
+    });
+
+    serde_json::to_string(&records).unwrap_or_else(|_| "[]".to_string())
+}
+
+#[ic_cdk::update]
+fn import_tasks_json(data: String) -> Result<u64, String> {
+    let records: Vec<TaskwarriorRecord> =
+        serde_json::from_str(&data).map_err(|_| String::from(TaskError::InvalidInput))?;
+
+    // Parse and validate the whole batch into fully-built tasks (id left as
+    // a placeholder) before touching any state, so a malformed record
+    // partway through the batch can't leave earlier records already
+    // committed with NEXT_ID advanced out from under them.
+    let mut pending_tasks = Vec::new();
+    for record in records {
+        if record.status == "deleted" {
+            continue;
+        }
+        if record.status != "pending" && record.status != "completed" {
+            return Err(TaskError::InvalidInput.into());
+        }
+        if record.description.is_empty() {
+            return Err(TaskError::InvalidInput.into());
+        }
+
+        let created_at = parse_taskwarrior_time(&record.entry)?;
+        let updated_at = parse_taskwarrior_time(&record.modified)?;
+        let annotations = record
+            .annotations
+            .iter()
+            .map(|annotation| Ok((parse_taskwarrior_time(&annotation.entry)?, annotation.description.clone())))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        pending_tasks.push(Task {
+            id: 0,
+            uuid: record.uuid,
+            title: record.udas.get("title").cloned().unwrap_or_else(|| record.description.clone()),
+            description: record.description,
+            status: if record.status == "completed" { TaskStatus::Completed } else { TaskStatus::Pending },
+            is_important: record.udas.get("is_important").map(|value| value == "true").unwrap_or(false),
+            created_at,
+            updated_at,
+            started_at: None,
+            due_at: None,
+            dependencies: Vec::new(),
+            annotations,
+        });
+    }
+
+    let imported = pending_tasks.len() as u64;
+    let start_id = allocate_task_ids(imported)?;
+    for (offset, mut task) in pending_tasks.into_iter().enumerate() {
+        task.id = start_id + offset as u64;
+        insert_task_with_indexes(task);
+    }
+
+    Ok(imported)
+}
+
+#[ic_cdk::pre_upgrade]
+fn pre_upgrade() {
+    let tasks: Vec<(u64, Task)> = TASKS.with(|tasks| {
+        tasks.borrow().iter().map(|(id, task)| (*id, task.clone())).collect()
+    });
+    let next_id = NEXT_ID.with(|id| id.get());
+
+    ic_cdk::storage::stable_save((tasks, next_id)).expect("failed to save state to stable memory");
+}
+
+#[ic_cdk::post_upgrade]
+fn post_upgrade() {
+    let (tasks, next_id): (Vec<(u64, Task)>, u64) = ic_cdk::storage::stable_restore()
+        .expect("failed to restore state from stable memory");
+
+    // insert_task_with_indexes is the one place that knows how to bring a
+    // task into every bitmap/index; reuse it here instead of re-deriving
+    // that bookkeeping by hand.
+    for (_, task) in &tasks {
+        insert_task_with_indexes(task.clone());
+    }
+
+    // insert_task_with_indexes only adds a graph node per task; rebuild the
+    // dependency edges now that every node exists.
+    DEP_GRAPH.with(|graph| {
+        let mut graph = graph.borrow_mut();
+        for (id, task) in &tasks {
+            for dep_id in &task.dependencies {
+                graph.add_edge(*dep_id, *id, ());
+            }
+        }
+    });
+
+    NEXT_ID.with(|id| id.set(next_id));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NANOS_PER_WEEK: u64 = NANOS_PER_DAY * 7;
+
+    #[test]
+    fn creates_cycle_detects_existing_path() {
+        let mut graph: DiGraphMap<u64, ()> = DiGraphMap::new();
+        graph.add_edge(1, 2, ());
+        graph.add_edge(2, 3, ());
+
+        // 1 can already reach 3, so adding 3 -> 1 would close a cycle.
+        assert!(creates_cycle(&graph, 1, 3));
+    }
+
+    #[test]
+    fn creates_cycle_allows_unconnected_tasks() {
+        let mut graph: DiGraphMap<u64, ()> = DiGraphMap::new();
+        graph.add_edge(1, 2, ());
+
+        assert!(!creates_cycle(&graph, 3, 1));
+    }
+
+    #[test]
+    fn get_task_order_returns_dependencies_before_dependents() {
+        DEP_GRAPH.with(|graph| {
+            let mut graph = graph.borrow_mut();
+            graph.add_edge(1, 2, ());
+            graph.add_edge(2, 3, ());
+        });
+
+        let order = get_task_order().expect("acyclic graph should sort");
+        let pos = |id: u64| order.iter().position(|&x| x == id).unwrap();
+        assert!(pos(1) < pos(2));
+        assert!(pos(2) < pos(3));
+    }
+
+    #[test]
+    fn get_task_order_reports_cycle() {
+        DEP_GRAPH.with(|graph| {
+            let mut graph = graph.borrow_mut();
+            graph.add_edge(1, 2, ());
+            graph.add_edge(2, 1, ());
+        });
+
+        assert!(get_task_order().is_err());
+    }
+
+    #[test]
+    fn parse_due_date_today_and_tomorrow() {
+        let now = 1_000 * NANOS_PER_SEC;
+        assert_eq!(parse_due_date("today", now).unwrap(), now);
+        assert_eq!(parse_due_date("TOMORROW", now).unwrap(), now + NANOS_PER_DAY);
+    }
+
+    #[test]
+    fn parse_due_date_relative_offsets() {
+        let now = 0;
+        assert_eq!(parse_due_date("in 3 hours", now).unwrap(), 3 * NANOS_PER_HOUR);
+        assert_eq!(parse_due_date("in 2 days", now).unwrap(), 2 * NANOS_PER_DAY);
+        assert_eq!(parse_due_date("in 1 week", now).unwrap(), NANOS_PER_WEEK);
+    }
+
+    #[test]
+    fn parse_due_date_next_weekday() {
+        // Unix epoch (0) is a Thursday.
+        let thursday = 0;
+        assert_eq!(parse_due_date("friday", thursday).unwrap(), thursday + NANOS_PER_DAY);
+        assert_eq!(parse_due_date("next thursday", thursday).unwrap(), thursday + NANOS_PER_WEEK);
+    }
+
+    #[test]
+    fn parse_due_date_rejects_garbage() {
+        assert!(parse_due_date("whenever", 0).is_err());
+        assert!(parse_due_date("in three days", 0).is_err());
+    }
+
+    #[test]
+    fn parse_due_date_rejects_overflowing_offset() {
+        assert!(parse_due_date("in 20000000000 days", 0).is_err());
+        assert!(parse_due_date("in 18446744073709551615 hours", u64::MAX).is_err());
+    }
+
+    fn sample_task(id: u64, created_at: u64, updated_at: u64) -> Task {
+        Task {
+            id,
+            uuid: format!("uuid-{id}"),
+            title: "title".to_string(),
+            description: "description".to_string(),
+            status: TaskStatus::Pending,
+            is_important: false,
+            created_at,
+            updated_at,
+            started_at: None,
+            due_at: None,
+            dependencies: Vec::new(),
+            annotations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn created_index_reflects_insertion() {
+        insert_task_with_indexes(sample_task(1, 100, 100));
+
+        assert!(get_tasks_created_after(50).iter().any(|t| t.id == 1));
+        assert!(!get_tasks_created_after(100).iter().any(|t| t.id == 1));
+    }
+
+    #[test]
+    fn update_path_reindexes_updated_at() {
+        insert_task_with_indexes(sample_task(1, 100, 100));
+
+        TASKS.with(|tasks| {
+            let mut tasks = tasks.borrow_mut();
+            let task = tasks.get_mut(&1).unwrap();
+            reindex_updated_at(task, 1, 500);
+        });
+
+        assert!(get_tasks_updated_after(100).iter().any(|t| t.id == 1));
+        assert!(!get_tasks_updated_after(500).iter().any(|t| t.id == 1));
+        // The stale bucket under the old timestamp must be gone, not just
+        // superseded, or it leaks the id forever.
+        assert!(UPDATED_INDEX.with(|index| !index.borrow().contains_key(&100)));
+    }
+
+    #[test]
+    fn import_export_round_trip_preserves_task_fields() {
+        let input = serde_json::json!([
+            {
+                "uuid": "11111111-1111-1111-1111-111111111111",
+                "status": "pending",
+                "entry": "20240101T000000Z",
+                "modified": "20240102T000000Z",
+                "description": "round trip task",
+                "title": "round trip task",
+                "is_important": "true"
+            }
+        ])
+        .to_string();
+
+        let imported = import_tasks_json(input).expect("import should succeed");
+        assert_eq!(imported, 1);
+
+        let exported = export_tasks_json();
+        let records: Vec<TaskwarriorRecord> = serde_json::from_str(&exported).unwrap();
+        let record = records
+            .iter()
+            .find(|r| r.description == "round trip task")
+            .expect("imported task should round-trip through export");
+
+        assert_eq!(record.status, "pending");
+        assert_eq!(record.udas.get("title").map(String::as_str), Some("round trip task"));
+        assert_eq!(record.udas.get("is_important").map(String::as_str), Some("true"));
+    }
 }
 
 // need this to generate candid